@@ -1,11 +1,26 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
 use std::{fs, io};
 use chrono::NaiveDateTime;
 use clap::Parser;
-use rustfft::{FftPlanner, num_complex::Complex};
+use gbdt::config::Config;
+use gbdt::decision_tree::{Data, DataVec};
+use gbdt::gradient_boost::GBDT;
+use rustfft::{Fft, FftPlanner, num_complex::Complex};
 use serde::Deserialize;
 
 type DataPoint = f32;
 
+/// Boundary extrapolation method used by `extrapolate` before computing the saliency map.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+enum ExtrapolationMethod {
+    /// Pads both ends with a single value predicted from the average gradient of the last `m` points.
+    Linear,
+    /// Fits the degree-`m-1` Lagrange polynomial through the last (resp. first) `m` points and
+    /// evaluates it at the extrapolated indices.
+    Poly,
+}
+
 #[derive(Parser)]
 struct Cli {
     /// Path to the input CSV file. If not provided or "-", it reads from the standard input.
@@ -30,6 +45,39 @@ struct Cli {
     /// Number of extrapolated points. 0 for disabling extrapolation.
     #[clap(short, long, default_value = "5")]
     k: usize,
+
+    /// Boundary extrapolation method.
+    #[clap(long, value_enum, default_value = "linear")]
+    extrapolation: ExtrapolationMethod,
+
+    /// Path to a CSV of labeled anomaly segments (`from,to` timestamp ranges). When given, a
+    /// classifier is trained on the data instead of running inference, and saved to `--model`.
+    #[clap(long)]
+    train: Option<String>,
+
+    /// Path to read (inference) or write (`--train`) the classifier model. When set without
+    /// `--train`, the model's prediction replaces the `score > t` threshold in `detect`.
+    #[clap(long)]
+    model: Option<String>,
+
+    /// Emit contiguous anomaly segments instead of per-point flags.
+    #[clap(long)]
+    segments: bool,
+
+    /// Maximum number of non-anomalous points allowed between two anomaly runs for them to be
+    /// merged into the same segment. Only used with `--segments`.
+    #[clap(long, default_value = "1")]
+    gap: usize,
+
+    /// Run in streaming mode: read rows one at a time from the input and emit a verdict for each
+    /// as soon as it arrives, instead of batching the whole input through one global FFT.
+    /// No verdict is printed for the first `--window - 1` rows, until the window is full.
+    #[clap(long)]
+    stream: bool,
+
+    /// Size of the sliding window recomputed on every new row in `--stream` mode. Must be at least 1.
+    #[clap(long, default_value = "100")]
+    window: usize,
 }
 
 #[derive(Debug, Deserialize, PartialEq)]
@@ -39,6 +87,25 @@ struct Record {
     value: DataPoint,
 }
 
+/// A contiguous run of anomalous points, as emitted by `--segments`.
+#[derive(Debug, PartialEq)]
+struct Segment {
+    from_time: NaiveDateTime,
+    to_time: NaiveDateTime,
+    peak_score: DataPoint,
+    mean_score: DataPoint,
+    length: usize,
+}
+
+/// A labeled anomaly segment used to train the supervised classifier.
+#[derive(Debug, Deserialize, PartialEq)]
+struct Label {
+    #[serde(rename = "from", with = "timestamp_format")]
+    from: NaiveDateTime,
+    #[serde(rename = "to", with = "timestamp_format")]
+    to: NaiveDateTime,
+}
+
 fn main() {
     let cli = Cli::parse();
 
@@ -57,10 +124,46 @@ fn main() {
     let t = cli.t;
     let m = cli.m;
     let k = cli.k;
+    let extrapolation = cli.extrapolation;
+
+    if cli.stream {
+        if cli.window == 0 {
+            eprintln!("error: --window must be at least 1");
+            std::process::exit(1);
+        }
+        run_stream(input, q, z, t, cli.window);
+        return;
+    }
 
     let records = read_data(input);
     let (times, data): (Vec<_>, Vec<_>) = records.iter().map(|r| (r.time, r.value)).unzip();
-    let (map, score, anomalies) = detect(&data, q, z, t, m, k);
+
+    if let Some(labels_path) = cli.train.as_deref() {
+        let model_path = cli.model.as_deref().expect("--model is required together with --train");
+        let labels = read_labels(Box::new(io::BufReader::new(fs::File::open(labels_path).unwrap())));
+        let (saliency_map, score, _) = detect(&data, q, z, t, m, k, extrapolation);
+        train_model(&times, &data, &saliency_map, &score, &labels, model_path);
+        return;
+    }
+
+    let (map, score, anomalies) = match cli.model.as_deref() {
+        Some(model_path) => {
+            let (saliency_map, score, _) = detect(&data, q, z, t, m, k, extrapolation);
+            let anomalies = predict_anomalies(&data, &saliency_map, &score, model_path);
+            (saliency_map, score, anomalies)
+        }
+        None => detect(&data, q, z, t, m, k, extrapolation),
+    };
+
+    if cli.segments {
+        let segments = collapse_segments(&times, &score, &anomalies, cli.gap);
+        println!("from_time,to_time,peak_score,mean_score,length");
+        for s in &segments {
+            println!("{},{},{},{},{}", s.from_time, s.to_time, s.peak_score, s.mean_score, s.length);
+        }
+        return;
+    }
+
     println!("Time,value,saliency,score,output");
     for ((((time, value), spectrum), score), anomaly) in times.iter().zip(data.iter()).zip(map.iter()).zip(score.iter()).zip(anomalies.iter()) {
         println!("{},{},{},{},{}", time, value, spectrum, score, if *anomaly { 1 } else { 0 });
@@ -73,16 +176,125 @@ fn read_data(input: Box<dyn io::BufRead>) -> Vec<Record> {
     rdr.deserialize().map(|result| result.unwrap()).collect()
 }
 
+/// Collapses a per-point `anomalies` vector into contiguous segments, merging anomaly runs
+/// separated by at most `gap` non-anomalous points.
+fn collapse_segments(times: &[NaiveDateTime], score: &[DataPoint], anomalies: &[bool], gap: usize) -> Vec<Segment> {
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+    for (i, &is_anomaly) in anomalies.iter().enumerate() {
+        if is_anomaly {
+            current = Some((current.map_or(i, |(start, _)| start), i));
+        } else if let Some(run) = current.take() {
+            runs.push(run);
+        }
+    }
+    if let Some(run) = current {
+        runs.push(run);
+    }
+
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in runs {
+        match merged.last_mut() {
+            Some((_, prev_end)) if start - *prev_end - 1 <= gap => *prev_end = end,
+            _ => merged.push((start, end)),
+        }
+    }
+
+    merged.into_iter().map(|(start, end)| {
+        let slice = &score[start..=end];
+        Segment {
+            from_time: times[start],
+            to_time: times[end],
+            peak_score: slice.iter().cloned().fold(DataPoint::MIN, DataPoint::max),
+            mean_score: slice.iter().sum::<DataPoint>() / slice.len() as DataPoint,
+            length: end - start + 1,
+        }
+    }).collect()
+}
+
+fn read_labels(input: Box<dyn io::BufRead>) -> Vec<Label> {
+    let mut rdr = csv::Reader::from_reader(input);
+    rdr.deserialize().map(|result| result.unwrap()).collect()
+}
+
+/// Number of points in the short FFT window used for supervised-mode features.
+const FEATURE_WINDOW: usize = 64;
+
+/// Builds the feature vector the classifier is trained/queried on for the point at `idx`:
+/// its saliency value, its score, and the magnitudes of a `FEATURE_WINDOW`-point FFT taken
+/// over the raw series centered on `idx` (zero-padded at the boundaries).
+fn build_feature_vector(data: &[DataPoint], saliency: DataPoint, score: DataPoint, idx: usize) -> Vec<f32> {
+    let half = FEATURE_WINDOW / 2;
+    let start = idx.saturating_sub(half);
+    let end = (idx + (FEATURE_WINDOW - half)).min(data.len());
+    let pad = half.saturating_sub(idx);
+
+    let mut window = vec![0.0; FEATURE_WINDOW];
+    for (i, &v) in data[start..end].iter().enumerate() {
+        window[pad + i] = v;
+    }
+
+    let mut features = Vec::with_capacity(2 + FEATURE_WINDOW);
+    features.push(saliency);
+    features.push(score);
+    features.extend(fft_magnitudes(&window));
+    features
+}
+
+/// Returns the magnitudes of the forward FFT of `window`.
+fn fft_magnitudes(window: &[DataPoint]) -> Vec<DataPoint> {
+    let mut planner = FftPlanner::new();
+    let fft = planner.plan_fft_forward(window.len());
+    let mut freq = window.iter().map(|x| Complex::new(*x, 0.0)).collect::<Vec<_>>();
+    fft.process(&mut freq);
+    freq.iter().map(|c| c.norm()).collect()
+}
+
+/// Trains a gradient-boosted decision tree classifier on `data`, labeling each point `1` if
+/// its timestamp falls within one of `labels` and `-1` otherwise, and saves it to `model_path`.
+fn train_model(times: &[NaiveDateTime], data: &[DataPoint], saliency_map: &[DataPoint], score: &[DataPoint], labels: &[Label], model_path: &str) {
+    let mut cfg = Config::new();
+    cfg.set_feature_size(2 + FEATURE_WINDOW);
+    cfg.set_max_depth(4);
+    cfg.set_iterations(100);
+    cfg.set_shrinkage(0.1);
+    cfg.set_loss("LogLikelyhood");
+
+    let mut train_data: DataVec = times.iter().enumerate().map(|(idx, time)| {
+        // `LogLikelyhood` loss expects labels in {-1, 1}; a 0/1 encoding gives the negative
+        // class a zero gradient and it never trains.
+        let label = if labels.iter().any(|l| *time >= l.from && *time <= l.to) { 1.0 } else { -1.0 };
+        let feature = build_feature_vector(data, saliency_map[idx], score[idx], idx);
+        Data::new_training_data(feature, 1.0, label, None)
+    }).collect();
+
+    let mut gbdt = GBDT::new(&cfg);
+    gbdt.fit(&mut train_data);
+    gbdt.save_model(model_path).expect("failed to save model");
+}
+
+/// Loads the classifier at `model_path` and predicts anomalies for `data`, replacing the
+/// `score > t` threshold used in the unsupervised path.
+fn predict_anomalies(data: &[DataPoint], saliency_map: &[DataPoint], score: &[DataPoint], model_path: &str) -> Vec<bool> {
+    let gbdt = GBDT::load_model(model_path).expect("failed to load model");
+    let test_data: DataVec = (0..data.len()).map(|idx| {
+        let feature = build_feature_vector(data, saliency_map[idx], score[idx], idx);
+        Data::new_test_data(feature, None)
+    }).collect();
+    gbdt.predict(&test_data).iter().map(|&p| p > 0.5).collect()
+}
+
 /// Detects the anomalies in the `data` using Spectral Residual method.
 /// `q` is the window size for calculating a saliency map.
 /// `z` is the window size for calculating the average of the saliency map which is used for scoring.
 /// `t` is the threshold that determines if a data point is an anomaly.
 /// `m` is the number of preceding points considered for extrapolation.
 /// `k` is the number of extrapolated points.
+/// `extrapolation` selects the boundary extrapolation method.
 /// Returns a vector of booleans where `true` indicates an anomaly. Its size is the same as the input `data`.
-fn detect(data: &[DataPoint], q: usize, z: usize, t: DataPoint, m: usize, k: usize) -> (Vec<DataPoint>, Vec<DataPoint>, Vec<bool>) {
+fn detect(data: &[DataPoint], q: usize, z: usize, t: DataPoint, m: usize, k: usize, extrapolation: ExtrapolationMethod) -> (Vec<DataPoint>, Vec<DataPoint>, Vec<bool>) {
     let n = data.len();
-    let data = extrapolate(data, m, k);
+    let data = extrapolate(data, m, k, extrapolation);
     // cut the extrapolated points
     let saliency_map = calculate_saliency_map(&data, q)[k..(n+k)].to_vec();
     let score = calculate_score(&saliency_map, z);
@@ -90,20 +302,54 @@ fn detect(data: &[DataPoint], q: usize, z: usize, t: DataPoint, m: usize, k: usi
     (saliency_map, score, result)
 }
 
-/// Extrapolates the data.
-/// The extrapolated point x_(n+1) is calculated by x_(n-m+1) + g * m,
-/// where g is the average gradient of the last m points, and m is the number of preceding points considered.
-/// `k` points are extrapolated.
+/// Extrapolates the data, padding `k` points on both ends using `method`.
+/// `Linear` pads both ends with a single point x_(n+1) = x_(n-m+1) + g * m, where g is the
+/// average gradient of the last m points, and m is the number of preceding points considered.
+/// `Poly` fits the degree-`m-1` Lagrange polynomial through the last (resp. first) `m` points
+/// and evaluates it at the `k` extrapolated indices on each side.
 /// If `k` is 0, it returns the original data.
-fn extrapolate(data: &[DataPoint], m: usize, k: usize) -> Vec<DataPoint> {
+fn extrapolate(data: &[DataPoint], m: usize, k: usize, method: ExtrapolationMethod) -> Vec<DataPoint> {
     if k == 0 {
         return data.to_vec();
     }
     assert!(m <= data.len(), "m must be less than or equal to the length of the data");
+    match method {
+        ExtrapolationMethod::Linear => {
+            let last_idx = data.len() - 1;
+            let g = (last_idx.wrapping_sub(m)..last_idx).map(|i| gradient(data, last_idx, i)).sum::<DataPoint>() / m as DataPoint;
+            let extra_value = data[last_idx.wrapping_sub(m).wrapping_add(1)] + g * m as DataPoint;
+            [vec![extra_value; k], data.to_vec(), vec![extra_value; k]].concat()
+        }
+        ExtrapolationMethod::Poly => {
+            [lagrange_extrapolate_left(data, m, k), data.to_vec(), lagrange_extrapolate_right(data, m, k)].concat()
+        }
+    }
+}
+
+/// Evaluates the Lagrange interpolating polynomial through `points` (pairs of `(x, y)`) at `x`.
+fn lagrange_interpolate(points: &[(DataPoint, DataPoint)], x: DataPoint) -> DataPoint {
+    points.iter().enumerate().map(|(j, &(xj, yj))| {
+        let basis = points.iter().enumerate()
+            .filter(|&(i, _)| i != j)
+            .map(|(_, &(xi, _))| (x - xi) / (xj - xi))
+            .product::<DataPoint>();
+        yj * basis
+    }).sum()
+}
+
+/// Extrapolates `k` points before the start of `data`, fitting the degree-`m-1` Lagrange
+/// polynomial through the first `m` points.
+fn lagrange_extrapolate_left(data: &[DataPoint], m: usize, k: usize) -> Vec<DataPoint> {
+    let points = (0..m).map(|i| (i as DataPoint, data[i])).collect::<Vec<_>>();
+    (1..=k).rev().map(|i| lagrange_interpolate(&points, -(i as DataPoint))).collect()
+}
+
+/// Extrapolates `k` points after the end of `data`, fitting the degree-`m-1` Lagrange
+/// polynomial through the last `m` points.
+fn lagrange_extrapolate_right(data: &[DataPoint], m: usize, k: usize) -> Vec<DataPoint> {
     let last_idx = data.len() - 1;
-    let g = (last_idx.wrapping_sub(m)..last_idx).map(|i| gradient(data, last_idx, i)).sum::<DataPoint>() / m as DataPoint;
-    let extra_value = data[last_idx.wrapping_sub(m).wrapping_add(1)] + g * m as DataPoint;
-    [vec![extra_value; k], data.to_vec(), vec![extra_value; k]].concat()
+    let points = (last_idx + 1 - m..=last_idx).map(|i| (i as DataPoint, data[i])).collect::<Vec<_>>();
+    (1..=k).map(|i| lagrange_interpolate(&points, (last_idx + i) as DataPoint)).collect()
 }
 
 /// Calculates the gradient of the two points.
@@ -112,11 +358,19 @@ fn gradient(data: &[DataPoint], x1: usize, x2: usize) -> DataPoint {
     (data[x2] - data[x1]) / x2.wrapping_sub(x1) as DataPoint
 }
 
-/// Calculates a saliency map.
+/// Calculates a saliency map, planning a fresh forward/inverse FFT pair for `data.len()`.
 fn calculate_saliency_map(data: &[DataPoint], q: usize) -> Vec<DataPoint> {
-    // perform FFT
     let mut planner = FftPlanner::new();
     let fft = planner.plan_fft_forward(data.len());
+    let ifft = planner.plan_fft_inverse(data.len());
+    calculate_saliency_map_with_plans(data, q, &fft, &ifft)
+}
+
+/// Calculates a saliency map using pre-planned forward/inverse FFTs. Callers that run over a
+/// fixed-length window on every iteration (e.g. `--stream`) should plan the FFTs once and
+/// reuse them here instead of re-planning every call.
+fn calculate_saliency_map_with_plans(data: &[DataPoint], q: usize, fft: &Arc<dyn Fft<DataPoint>>, ifft: &Arc<dyn Fft<DataPoint>>) -> Vec<DataPoint> {
+    // perform FFT
     let mut freq = data.iter().map(|x| Complex::new(*x, 0.0)).collect::<Vec<_>>();
     fft.process(&mut freq);
 
@@ -127,7 +381,6 @@ fn calculate_saliency_map(data: &[DataPoint], q: usize) -> Vec<DataPoint> {
     let spectral_residual = log_amp.iter().zip(average_log_amp.iter()).map(|(&x, &y)| x - y).collect::<Vec<_>>();
 
     // perform IFFT
-    let ifft = planner.plan_fft_inverse(data.len());
     let mut saliency_map = spectral_residual.iter().zip(phase.iter()).map(|(&r, &p)| Complex::from_polar(r, p).exp()).collect::<Vec<_>>();
     ifft.process(&mut saliency_map);
 
@@ -135,6 +388,48 @@ fn calculate_saliency_map(data: &[DataPoint], q: usize) -> Vec<DataPoint> {
     saliency_map.iter().map(|x| x.norm()).collect()
 }
 
+/// Runs `--stream` mode: keeps a bounded ring buffer of the last `window` rows read from
+/// `input`, and once the buffer is full, on every new row recomputes the saliency map and score
+/// over the window, printing the verdict for the newest point before reading the next line. No
+/// verdict is printed for the first `window - 1` rows. The forward/inverse
+/// FFT plans for `window` are built once and reused across iterations. `window` must be at
+/// least 1.
+fn run_stream(input: Box<dyn io::BufRead>, q: usize, z: usize, t: DataPoint, window: usize) {
+    let mut planner = FftPlanner::new();
+    let fft = planner.plan_fft_forward(window);
+    let ifft = planner.plan_fft_inverse(window);
+
+    let mut rdr = csv::Reader::from_reader(input);
+    let mut buffer: VecDeque<Record> = VecDeque::with_capacity(window);
+
+    println!("Time,value,saliency,score,output");
+    for result in rdr.deserialize::<Record>() {
+        let record = result.unwrap();
+        if buffer.len() == window {
+            buffer.pop_front();
+        }
+        buffer.push_back(record);
+        if buffer.len() < window {
+            continue;
+        }
+
+        let data = buffer.iter().map(|r| r.value).collect::<Vec<_>>();
+        let (saliency, score, anomaly) = stream_step(&data, q, z, t, &fft, &ifft);
+        let newest = &buffer[window - 1];
+        println!("{},{},{},{},{}", newest.time, newest.value, saliency, score, if anomaly { 1 } else { 0 });
+    }
+}
+
+/// Recomputes the saliency map and score over `window` (the current sliding-window contents)
+/// and returns the saliency, score and anomaly verdict for its newest (last) point. `window`
+/// must be non-empty.
+fn stream_step(window: &[DataPoint], q: usize, z: usize, t: DataPoint, fft: &Arc<dyn Fft<DataPoint>>, ifft: &Arc<dyn Fft<DataPoint>>) -> (DataPoint, DataPoint, bool) {
+    let saliency_map = calculate_saliency_map_with_plans(window, q, fft, ifft);
+    let score = calculate_score(&saliency_map, z);
+    let newest = window.len() - 1;
+    (saliency_map[newest], score[newest], score[newest] > t)
+}
+
 /// Calculates the scores of the saliency map.
 /// The scores are calculated by: (S - S_average) / S_average; where S is the saliency map and S_average is the local-averaged saliency map using window `z`.
 fn calculate_score(saliency_map: &[DataPoint], z: usize) -> Vec<DataPoint> {
@@ -208,19 +503,59 @@ mod tests {
         ]);
     }
 
+    #[test]
+    fn test_build_feature_vector() {
+        let data = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let features = build_feature_vector(&data, 0.5, 1.5, 2);
+        assert_eq!(features.len(), 2 + FEATURE_WINDOW);
+        assert_relative_eq!(features[0], 0.5);
+        assert_relative_eq!(features[1], 1.5);
+    }
+
+    #[test]
+    fn test_build_feature_vector_near_boundary() {
+        let data = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        // idx 0 is near the left edge, so the FFT window is zero-padded on that side.
+        let features = build_feature_vector(&data, 0.0, 0.0, 0);
+        assert_eq!(features.len(), 2 + FEATURE_WINDOW);
+    }
+
     #[test]
     fn test_detect() {
         let data = vec![1.0, 2.0, 3.0, 4.0, 5.0];
-        let (map, score, result) = detect(&data, 3, 5, 2.0, 5, 3);
+        let (map, score, result) = detect(&data, 3, 5, 2.0, 5, 3, ExtrapolationMethod::Linear);
         assert_eq!(map.len(), data.len());
         assert_eq!(score.len(), data.len());
         assert_eq!(result.len(), data.len());
     }
 
+    #[test]
+    fn test_collapse_segments() {
+        let times = (0..6).map(|i| NaiveDate::from_ymd_opt(2024, 11, 21).unwrap().and_hms_opt(0, i, 0).unwrap()).collect::<Vec<_>>();
+        let score = vec![0.0, 4.0, 1.0, 5.0, 6.0, 0.0];
+        let anomalies = vec![false, true, false, true, true, false];
+        let result = collapse_segments(&times, &score, &anomalies, 1);
+        assert_eq!(result, vec![
+            Segment { from_time: times[1], to_time: times[4], peak_score: 6.0, mean_score: (4.0 + 1.0 + 5.0 + 6.0) / 4.0, length: 4 },
+        ]);
+    }
+
+    #[test]
+    fn test_collapse_segments_no_merge() {
+        let times = (0..6).map(|i| NaiveDate::from_ymd_opt(2024, 11, 21).unwrap().and_hms_opt(0, i, 0).unwrap()).collect::<Vec<_>>();
+        let score = vec![0.0, 4.0, 1.0, 5.0, 6.0, 0.0];
+        let anomalies = vec![false, true, false, true, true, false];
+        let result = collapse_segments(&times, &score, &anomalies, 0);
+        assert_eq!(result, vec![
+            Segment { from_time: times[1], to_time: times[1], peak_score: 4.0, mean_score: 4.0, length: 1 },
+            Segment { from_time: times[3], to_time: times[4], peak_score: 6.0, mean_score: 5.5, length: 2 },
+        ]);
+    }
+
     #[test]
     fn test_extrapolate() {
         let data = vec![1.0, 2.0, 3.0, 4.0, 5.0];
-        let result = extrapolate(&data, 3, 2);
+        let result = extrapolate(&data, 3, 2, ExtrapolationMethod::Linear);
         let expected = [3.0, 3.0, 1.0, 2.0, 3.0, 4.0, 5.0, 3.0, 3.0];
         assert_eq!(result.len(), expected.len());
         for i in 0..result.len() {
@@ -228,6 +563,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_extrapolate_poly() {
+        // a straight line extrapolates to itself regardless of the polynomial degree
+        let data = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let result = extrapolate(&data, 3, 2, ExtrapolationMethod::Poly);
+        let expected = [-1.0, 0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
+        assert_eq!(result.len(), expected.len());
+        for i in 0..result.len() {
+            assert_relative_eq!(result[i], expected[i]);
+        }
+    }
+
+    #[test]
+    fn test_lagrange_interpolate() {
+        let points = [(0.0, 1.0), (1.0, 2.0), (2.0, 3.0)];
+        assert_relative_eq!(lagrange_interpolate(&points, 3.0), 4.0);
+        assert_relative_eq!(lagrange_interpolate(&points, -1.0), 0.0);
+    }
+
+    #[test]
+    fn test_stream_step() {
+        let window = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let mut planner = FftPlanner::new();
+        let fft = planner.plan_fft_forward(window.len());
+        let ifft = planner.plan_fft_inverse(window.len());
+        let (saliency, score, anomaly) = stream_step(&window, 3, 5, 2.0, &fft, &ifft);
+        let expected = detect(&window, 3, 5, 2.0, 0, 0, ExtrapolationMethod::Linear);
+        assert_relative_eq!(saliency, *expected.0.last().unwrap());
+        assert_relative_eq!(score, *expected.1.last().unwrap());
+        assert_eq!(anomaly, *expected.2.last().unwrap());
+    }
+
     #[test]
     fn test_calculate_saliency_map() {
         let data = vec![1.0, 2.0, 3.0, 4.0, 5.0];